@@ -0,0 +1,133 @@
+use crate::{StorageError, UnwrapJsVal};
+
+/// The primitive operations a [`Storage`](crate::Storage) needs from whatever it's backed by.
+///
+/// The crate ships [`WebBackend`], which wraps a real `web_sys::Storage`, and (behind the
+/// `mock` feature) [`MemoryBackend`](mock::MemoryBackend), an in-memory stand-in for unit-testing
+/// persistence logic without a browser.
+pub trait Backend {
+    /// Get the key for the `idx`th item in the storage.
+    fn key(&self, idx: usize) -> Option<String>;
+
+    /// Get a record from the storage if present, without panicking on error.
+    fn get(&self, key: &str) -> Result<Option<String>, StorageError>;
+
+    /// Set a record in the storage and return the old record with the same key, if present.
+    fn set(&self, key: &str, val: &str) -> Result<Option<String>, StorageError>;
+
+    /// Remove a record from the storage and return it, if present.
+    fn remove(&self, key: &str) -> Result<Option<String>, StorageError>;
+
+    /// Remove all records from the storage.
+    fn clear(&self) -> Result<(), StorageError>;
+
+    /// Get the number of records in the storage.
+    fn len(&self) -> usize;
+}
+
+/// A [`Backend`] wrapping a real `web_sys::Storage` (`window.localStorage`/`sessionStorage`).
+#[derive(Debug, Clone)]
+pub struct WebBackend {
+    inner: web_sys::Storage,
+}
+
+impl WebBackend {
+    pub fn new(inner: web_sys::Storage) -> Self {
+        // Casts only work when sizeof(usize) >= sizeof(u32), which is ok on wasm.
+        debug_assert!(std::mem::size_of::<usize>() >= std::mem::size_of::<u32>());
+        WebBackend { inner }
+    }
+}
+
+impl Backend for WebBackend {
+    fn key(&self, idx: usize) -> Option<String> {
+        // futureproof for 64 bit wasm
+        if idx > u32::max_value() as usize {
+            wasm_bindgen::throw_str("u32 overflow on position");
+        }
+        self.inner.key(idx as u32).throw_err()
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, StorageError> {
+        self.inner.get_item(key).map_err(StorageError::from_js)
+    }
+
+    fn set(&self, key: &str, val: &str) -> Result<Option<String>, StorageError> {
+        let old = self.get(key)?;
+        self.inner
+            .set_item(key, val)
+            .map_err(StorageError::from_js)?;
+        Ok(old)
+    }
+
+    fn remove(&self, key: &str) -> Result<Option<String>, StorageError> {
+        let old = self.get(key)?;
+        self.inner
+            .remove_item(key)
+            .map_err(StorageError::from_js)?;
+        Ok(old)
+    }
+
+    fn clear(&self) -> Result<(), StorageError> {
+        self.inner.clear().map_err(StorageError::from_js)
+    }
+
+    fn len(&self) -> usize {
+        self.inner.length().throw_err() as usize
+    }
+}
+
+#[cfg(feature = "mock")]
+mod mock {
+    use std::cell::RefCell;
+
+    use indexmap::IndexMap;
+
+    use super::Backend;
+    use crate::StorageError;
+
+    /// An in-memory [`Backend`], for unit-testing persistence logic under a normal `cargo test`,
+    /// without a headless browser.
+    ///
+    /// Iteration order matches the documented semantics of [`Storage::iter`](crate::Storage::iter):
+    /// most-recently-modified first.
+    #[derive(Debug, Default)]
+    pub struct MemoryBackend {
+        entries: RefCell<IndexMap<String, String>>,
+    }
+
+    impl Backend for MemoryBackend {
+        fn key(&self, idx: usize) -> Option<String> {
+            self.entries.borrow().get_index(idx).map(|(k, _)| k.clone())
+        }
+
+        fn get(&self, key: &str) -> Result<Option<String>, StorageError> {
+            Ok(self.entries.borrow().get(key).cloned())
+        }
+
+        fn set(&self, key: &str, val: &str) -> Result<Option<String>, StorageError> {
+            let mut entries = self.entries.borrow_mut();
+            let old = entries.shift_remove(key);
+            entries.insert(key.to_string(), val.to_string());
+            let newest = entries.len() - 1;
+            entries.move_index(newest, 0);
+            Ok(old)
+        }
+
+        fn remove(&self, key: &str) -> Result<Option<String>, StorageError> {
+            Ok(self.entries.borrow_mut().shift_remove(key))
+        }
+
+        fn clear(&self) -> Result<(), StorageError> {
+            self.entries.borrow_mut().clear();
+            Ok(())
+        }
+
+        fn len(&self) -> usize {
+            self.entries.borrow().len()
+        }
+    }
+}
+
+#[cfg(feature = "mock")]
+pub use mock::MemoryBackend;
@@ -0,0 +1,296 @@
+use crate::{Backend, Storage, StorageError};
+
+/// Unit a chunk size is rounded up to, the way `store_valid_value_size` rounds block-store
+/// allocations up to a page boundary.
+const PAGE_SIZE: usize = 4 * 1024;
+
+/// Bytes added to a value's length before it is rounded up to whole pages to pick a chunk size.
+/// It is added once per value, not reserved inside each chunk: chunks are filled up to the full
+/// chunk size.
+const CHUNK_HEADER: usize = 8;
+
+/// Upper bound on how many pages a single chunk may span, so one huge value can't produce one
+/// huge segment and defeat the point of chunking.
+const MAX_PAGES_PER_CHUNK: usize = 16;
+
+/// Pick a page-aligned chunk size for a value of `len` bytes, the way `store_valid_value_size`
+/// picks a page count for a block-store allocation: round up to a page, then clamp.
+fn chunk_size_for(len: usize) -> usize {
+    let pages = (len + CHUNK_HEADER + PAGE_SIZE - 1) / PAGE_SIZE;
+    let pages = pages.clamp(1, MAX_PAGES_PER_CHUNK);
+    pages * PAGE_SIZE
+}
+
+/// Split `s` into chunks of at most `max_len` bytes, without splitting a multi-byte UTF-8
+/// character across chunks.
+fn str_chunks(s: &str, max_len: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = s;
+    while !rest.is_empty() {
+        let mut end = max_len.min(rest.len());
+        while end > 0 && !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        if end == 0 {
+            // max_len is smaller than this char; take it whole rather than loop forever.
+            end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        }
+        let (chunk, remainder) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = remainder;
+    }
+    chunks
+}
+
+/// Key for the `idx`th segment of `generation` of a chunked value stored at `base`.
+///
+/// The generation is part of the key precisely so a new value is never written under the same
+/// keys an old, still-current value is using: the two generations' segments can never collide or
+/// overwrite each other in place.
+fn chunk_key(base: &str, generation: u64, idx: usize) -> String {
+    format!("{}#{}#{}", base, generation, idx)
+}
+
+/// Parse a manifest of the form `"<generation>:<chunk count>:<total byte length>"`.
+fn parse_manifest(manifest: &str) -> Option<(u64, usize, usize)> {
+    let mut parts = manifest.splitn(3, ':');
+    let generation = parts.next()?.parse().ok()?;
+    let count = parts.next()?.parse().ok()?;
+    let total_len = parts.next()?.parse().ok()?;
+    Some((generation, count, total_len))
+}
+
+impl<B: Backend> Storage<B> {
+    /// Store a value that may be too large for a single entry, by splitting it into fixed-size
+    /// segments under derived keys (`"<key>#<generation>#0"`, `"<key>#<generation>#1"`, ...) plus
+    /// a manifest at `key` recording the generation, chunk count and total length.
+    ///
+    /// Read it back with [`get_chunked`](Storage::get_chunked), and clean it up with
+    /// [`remove_chunked`](Storage::remove_chunked) rather than [`remove`](Storage::remove), which
+    /// only removes the manifest.
+    ///
+    /// Each call writes its segments under a fresh generation, so they never share keys with (and
+    /// can never overwrite) whatever generation is currently live. The manifest write is the only
+    /// step that commits the new generation; it either fully succeeds, making the new generation
+    /// current, or fully fails, leaving the old generation (and its manifest) exactly as it was.
+    /// Either way, any segments written for this call that didn't make it into a committed
+    /// generation are rolled back, so the store is never left holding half of a value, and a
+    /// failed overwrite can never lose the value that was already there.
+    pub fn set_chunked(&self, key: &str, val: &str) -> Result<(), StorageError> {
+        let old = self.try_get(key)?;
+        let old_manifest = old.as_deref().and_then(parse_manifest);
+        let generation = old_manifest.map_or(0, |(generation, _, _)| generation.wrapping_add(1));
+
+        let chunk_size = chunk_size_for(val.len());
+        let segments = str_chunks(val, chunk_size);
+
+        let mut written: Vec<String> = Vec::with_capacity(segments.len());
+        for (idx, segment) in segments.iter().enumerate() {
+            let seg_key = chunk_key(key, generation, idx);
+            if let Err(e) = self.try_set(&seg_key, segment) {
+                for written_key in &written {
+                    let _ = self.try_remove(written_key);
+                }
+                return Err(e);
+            }
+            written.push(seg_key);
+        }
+
+        let manifest = format!("{}:{}:{}", generation, segments.len(), val.len());
+        if let Err(e) = self.try_set(key, &manifest) {
+            for written_key in &written {
+                let _ = self.try_remove(written_key);
+            }
+            return Err(e);
+        }
+
+        // The new generation is now current; clean up the previous one on a best-effort basis.
+        // A failure here just leaves orphaned keys behind, it can't corrupt the value we just
+        // committed.
+        if let Some((old_generation, old_count, _)) = old_manifest {
+            for idx in 0..old_count {
+                let _ = self.try_remove(&chunk_key(key, old_generation, idx));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read back a value written with [`set_chunked`](Storage::set_chunked).
+    ///
+    /// Returns `None` if there's no value at `key`, or if the stored segments don't match the
+    /// manifest (e.g. a segment was removed directly instead of through
+    /// [`remove_chunked`](Storage::remove_chunked)).
+    pub fn get_chunked(&self, key: &str) -> Result<Option<String>, StorageError> {
+        let manifest = match self.try_get(key)? {
+            Some(m) => m,
+            None => return Ok(None),
+        };
+        let (generation, count, total_len) = match parse_manifest(&manifest) {
+            Some(parsed) => parsed,
+            None => return Ok(None),
+        };
+
+        let mut val = String::with_capacity(total_len);
+        for idx in 0..count {
+            match self.try_get(&chunk_key(key, generation, idx))? {
+                Some(segment) => val.push_str(&segment),
+                None => return Ok(None),
+            }
+        }
+        if val.len() != total_len {
+            return Ok(None);
+        }
+        Ok(Some(val))
+    }
+
+    /// Remove a value written with [`set_chunked`](Storage::set_chunked): the manifest and every
+    /// segment of its generation. Returns the value that was removed, if any.
+    pub fn remove_chunked(&self, key: &str) -> Result<Option<String>, StorageError> {
+        let old = self.get_chunked(key)?;
+
+        if let Some(manifest) = self.try_get(key)? {
+            if let Some((generation, count, _)) = parse_manifest(&manifest) {
+                for idx in 0..count {
+                    self.try_remove(&chunk_key(key, generation, idx))?;
+                }
+            }
+        }
+        self.try_remove(key)?;
+
+        Ok(old)
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use wasm_bindgen::JsValue;
+
+    use super::*;
+    use crate::MemoryBackend;
+
+    #[test]
+    fn round_trips_a_multi_chunk_value() {
+        let storage = Storage::new(MemoryBackend::default());
+        let big = "x".repeat(MAX_PAGES_PER_CHUNK * PAGE_SIZE * 2 + 123);
+        let chunk_size = chunk_size_for(big.len());
+        let expected_segments = (big.len() + chunk_size - 1) / chunk_size;
+        assert!(
+            expected_segments > 1,
+            "test input should need more than one chunk"
+        );
+
+        storage.set_chunked("blob", &big).unwrap();
+        assert_eq!(storage.count(), expected_segments + 1);
+        assert_eq!(
+            storage.get_chunked("blob").unwrap().as_deref(),
+            Some(big.as_str())
+        );
+
+        let removed = storage.remove_chunked("blob").unwrap();
+        assert_eq!(removed.as_deref(), Some(big.as_str()));
+        assert_eq!(storage.count(), 0);
+    }
+
+    /// A [`Backend`] wrapping a [`MemoryBackend`] that fails every `set` once a fixed write
+    /// budget is exhausted, to simulate a quota error partway through a chunked write.
+    struct FlakyBackend {
+        inner: MemoryBackend,
+        writes_left: Rc<Cell<usize>>,
+    }
+
+    impl FlakyBackend {
+        fn new(writes_left: usize) -> (Self, Rc<Cell<usize>>) {
+            let budget = Rc::new(Cell::new(writes_left));
+            (
+                FlakyBackend {
+                    inner: MemoryBackend::default(),
+                    writes_left: budget.clone(),
+                },
+                budget,
+            )
+        }
+    }
+
+    impl Backend for FlakyBackend {
+        fn key(&self, idx: usize) -> Option<String> {
+            self.inner.key(idx)
+        }
+
+        fn get(&self, key: &str) -> Result<Option<String>, StorageError> {
+            self.inner.get(key)
+        }
+
+        fn set(&self, key: &str, val: &str) -> Result<Option<String>, StorageError> {
+            let left = self.writes_left.get();
+            if left == 0 {
+                return Err(StorageError::QuotaExceeded(JsValue::NULL));
+            }
+            self.writes_left.set(left - 1);
+            self.inner.set(key, val)
+        }
+
+        fn remove(&self, key: &str) -> Result<Option<String>, StorageError> {
+            self.inner.remove(key)
+        }
+
+        fn clear(&self) -> Result<(), StorageError> {
+            self.inner.clear()
+        }
+
+        fn len(&self) -> usize {
+            self.inner.len()
+        }
+    }
+
+    #[test]
+    fn set_chunked_keeps_the_old_value_when_the_first_segment_write_fails() {
+        let (backend, budget) = FlakyBackend::new(10);
+        let storage = Storage::new(backend);
+
+        let first = "a".repeat(10);
+        storage.set_chunked("blob", &first).unwrap();
+
+        // Exhaust the write budget so the very first segment of the next write fails.
+        budget.set(0);
+        let second = "b".repeat(MAX_PAGES_PER_CHUNK * PAGE_SIZE * 2);
+        let err = storage.set_chunked("blob", &second).unwrap_err();
+        assert!(matches!(err, StorageError::QuotaExceeded(_)));
+
+        assert_eq!(
+            storage.get_chunked("blob").unwrap().as_deref(),
+            Some(first.as_str())
+        );
+    }
+
+    #[test]
+    fn set_chunked_keeps_the_old_value_when_the_manifest_write_fails() {
+        let (backend, budget) = FlakyBackend::new(10);
+        let storage = Storage::new(backend);
+
+        let first = "a".repeat(10);
+        storage.set_chunked("blob", &first).unwrap();
+
+        // Big enough to need exactly two segments (see `chunk_size_for`): budget for both
+        // segment writes to succeed, but not the manifest write that would commit them.
+        let second = "b".repeat(MAX_PAGES_PER_CHUNK * PAGE_SIZE + 1000);
+        let chunk_size = chunk_size_for(second.len());
+        let segment_count = (second.len() + chunk_size - 1) / chunk_size;
+        assert_eq!(segment_count, 2, "test input should need exactly two chunks");
+        budget.set(segment_count);
+
+        let err = storage.set_chunked("blob", &second).unwrap_err();
+        assert!(matches!(err, StorageError::QuotaExceeded(_)));
+
+        // The old value is untouched, and nothing from the failed write is live: only the old
+        // manifest and its one segment remain.
+        assert_eq!(
+            storage.get_chunked("blob").unwrap().as_deref(),
+            Some(first.as_str())
+        );
+        assert_eq!(storage.count(), 2);
+    }
+}
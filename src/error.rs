@@ -0,0 +1,104 @@
+use std::fmt;
+
+use wasm_bindgen::{JsCast, JsValue};
+
+/// Errors that can occur while reading or writing to a [`Storage`](crate::Storage).
+///
+/// These map onto the `DOMException`s the underlying Web Storage API can throw, with a
+/// catch-all variant for anything we don't recognise. Each variant keeps the original `JsValue`
+/// around so code that re-throws it (see [`Storage::set`](crate::Storage::set) and friends)
+/// hands callers back exactly what the browser threw, not a synthesized stand-in.
+#[derive(Debug, Clone)]
+pub enum StorageError {
+    /// The write would have exceeded the storage quota (usually ~5MB per origin).
+    QuotaExceeded(JsValue),
+    /// Storage is disabled or blocked, e.g. by privacy settings or a browser running in
+    /// private/incognito mode.
+    SecurityError(JsValue),
+    /// Anything else the browser threw that we don't have a dedicated variant for.
+    Js(JsValue),
+}
+
+impl StorageError {
+    /// Classify a rejected `JsValue` from a Web Storage call into a [`StorageError`].
+    pub(crate) fn from_js(err: JsValue) -> Self {
+        // Read the exception's name before moving `err` into a variant, so the `dyn_ref`
+        // borrow doesn't outlive it.
+        let name = err.dyn_ref::<web_sys::DomException>().map(|exc| exc.name());
+        match name.as_deref() {
+            Some("QuotaExceededError") => StorageError::QuotaExceeded(err),
+            Some("SecurityError") => StorageError::SecurityError(err),
+            _ => StorageError::Js(err),
+        }
+    }
+
+    /// Turn this error back into the original `JsValue`, for code that still wants to throw it.
+    pub(crate) fn into_js(self) -> JsValue {
+        match self {
+            StorageError::QuotaExceeded(err) => err,
+            StorageError::SecurityError(err) => err,
+            StorageError::Js(err) => err,
+        }
+    }
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::QuotaExceeded(_) => write!(f, "storage quota exceeded"),
+            StorageError::SecurityError(_) => {
+                write!(f, "storage is disabled or blocked (security error)")
+            }
+            StorageError::Js(err) => write!(f, "storage error: {:?}", err),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn dom_exception(name: &str) -> JsValue {
+        web_sys::DomException::new_with_message_and_name("boom", name)
+            .unwrap()
+            .into()
+    }
+
+    #[wasm_bindgen_test]
+    fn classifies_quota_exceeded() {
+        assert!(matches!(
+            StorageError::from_js(dom_exception("QuotaExceededError")),
+            StorageError::QuotaExceeded(_)
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    fn classifies_security_error() {
+        assert!(matches!(
+            StorageError::from_js(dom_exception("SecurityError")),
+            StorageError::SecurityError(_)
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    fn falls_back_to_js_for_unrecognised_dom_exceptions() {
+        assert!(matches!(
+            StorageError::from_js(dom_exception("NotFoundError")),
+            StorageError::Js(_)
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    fn falls_back_to_js_for_non_dom_exceptions() {
+        assert!(matches!(
+            StorageError::from_js(JsValue::from_str("not an exception")),
+            StorageError::Js(_)
+        ));
+    }
+}
@@ -0,0 +1,301 @@
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::StorageError;
+
+/// A storage area a [`Field`] can be backed by.
+///
+/// Implemented for [`Local`] and [`Session`], which route reads/writes through the fallible
+/// `try_*` functions of the [`local`](crate::local) and [`session`](crate::session) modules
+/// respectively, so a [`Field`] can surface a [`StorageError`] instead of panicking.
+pub trait Area {
+    fn get(key: &str) -> Result<Option<String>, StorageError>;
+    fn set(key: &str, val: &str) -> Result<Option<String>, StorageError>;
+    fn remove(key: &str) -> Result<Option<String>, StorageError>;
+}
+
+/// Backs a [`Field`] with `window.localStorage`.
+pub struct Local;
+
+impl Area for Local {
+    fn get(key: &str) -> Result<Option<String>, StorageError> {
+        crate::local::try_get(key)
+    }
+
+    fn set(key: &str, val: &str) -> Result<Option<String>, StorageError> {
+        crate::local::try_set(key, val)
+    }
+
+    fn remove(key: &str) -> Result<Option<String>, StorageError> {
+        crate::local::try_remove(key)
+    }
+}
+
+/// Backs a [`Field`] with `window.sessionStorage`.
+pub struct Session;
+
+impl Area for Session {
+    fn get(key: &str) -> Result<Option<String>, StorageError> {
+        crate::session::try_get(key)
+    }
+
+    fn set(key: &str, val: &str) -> Result<Option<String>, StorageError> {
+        crate::session::try_set(key, val)
+    }
+
+    fn remove(key: &str) -> Result<Option<String>, StorageError> {
+        crate::session::try_remove(key)
+    }
+}
+
+/// A strongly-typed value stored under a single key, layered over [`local`](crate::local) or
+/// [`session`](crate::session) storage.
+///
+/// `T` is serialized to a JSON string (via the `serde_json` feature) so callers don't have to
+/// hand-roll (de)serialization on top of the raw string key/value primitives. JSON is the only
+/// codec; a field cannot be given a different one.
+///
+///
+/// ```ignore
+/// let settings = Field::<Settings>::new("app.settings");
+/// settings.set(&Settings::default());
+/// let current = settings.get_or_default();
+/// ```
+pub struct Field<T, A = Local> {
+    key: String,
+    default: Option<T>,
+    _area: PhantomData<A>,
+}
+
+impl<T> Field<T, Local>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Create a field stored under `key` in local storage.
+    pub fn new(key: impl Into<String>) -> Self {
+        Field {
+            key: key.into(),
+            default: None,
+            _area: PhantomData,
+        }
+    }
+}
+
+impl<T, A> Field<T, A>
+where
+    T: Serialize + DeserializeOwned,
+    A: Area,
+{
+    /// Create a field whose key is namespaced, so several fields can share a prefix without
+    /// colliding, e.g. `Field::namespaced("app", "settings")` stores under `"app.settings"`.
+    pub fn namespaced(namespace: &str, key: &str) -> Self {
+        Field {
+            key: format!("{}.{}", namespace, key),
+            default: None,
+            _area: PhantomData,
+        }
+    }
+
+    /// Use session storage instead of local storage for this field.
+    pub fn in_session(self) -> Field<T, Session> {
+        Field {
+            key: self.key,
+            default: self.default,
+            _area: PhantomData,
+        }
+    }
+
+    /// Attach a default value, returned by [`Field::get_or_default`] when the key is absent or
+    /// fails to deserialize.
+    pub fn with_default(mut self, default: T) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Get the current value, if present and successfully deserialized.
+    ///
+    /// A deserialization failure (e.g. the key was written by an incompatible version) is
+    /// treated the same as an absent key: `None`, not a panic.
+    pub fn get(&self) -> Option<T> {
+        self.try_get()
+            .unwrap_or_else(|e| wasm_bindgen::throw_val(e.into_js()))
+    }
+
+    /// Get the current value, without panicking on error.
+    ///
+    /// This is the method to use if you want to handle
+    /// [`StorageError::QuotaExceeded`](crate::StorageError::QuotaExceeded) instead of aborting.
+    pub fn try_get(&self) -> Result<Option<T>, StorageError> {
+        Ok(A::get(&self.key)?.and_then(|raw| codec::decode(&raw)))
+    }
+
+    /// Set the current value.
+    pub fn set(&self, value: &T) {
+        self.try_set(value)
+            .unwrap_or_else(|e| wasm_bindgen::throw_val(e.into_js()))
+    }
+
+    /// Set the current value, without panicking on error.
+    pub fn try_set(&self, value: &T) -> Result<(), StorageError> {
+        A::set(&self.key, &codec::encode(value))?;
+        Ok(())
+    }
+
+    /// Remove the value, returning it if it was present.
+    pub fn remove(&self) -> Option<T> {
+        self.try_remove()
+            .unwrap_or_else(|e| wasm_bindgen::throw_val(e.into_js()))
+    }
+
+    /// Remove the value, returning it if it was present, without panicking on error.
+    pub fn try_remove(&self) -> Result<Option<T>, StorageError> {
+        Ok(A::remove(&self.key)?.and_then(|raw| codec::decode(&raw)))
+    }
+
+    /// Get the current value, falling back to `default` if absent or undecodable.
+    pub fn get_or(&self, default: T) -> T {
+        self.get().unwrap_or(default)
+    }
+}
+
+impl<T, A> Field<T, A>
+where
+    T: Serialize + DeserializeOwned + Clone + Default,
+    A: Area,
+{
+    /// Get the current value, falling back to the default given to [`Field::with_default`] if
+    /// one was set, then to `T::default()`.
+    pub fn get_or_default(&self) -> T {
+        self.get()
+            .or_else(|| self.default.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "serde_json")]
+mod codec {
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+
+    pub(super) fn encode<T: Serialize>(value: &T) -> String {
+        serde_json::to_string(value).expect("serializing a Field value should not fail")
+    }
+
+    pub(super) fn decode<T: DeserializeOwned>(raw: &str) -> Option<T> {
+        serde_json::from_str(raw).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    thread_local! {
+        static STORE: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+    }
+
+    /// An [`Area`] backed by a thread-local map, so `Field`'s own (de)serialization and
+    /// default-handling logic can be unit-tested without a browser.
+    struct TestArea;
+
+    impl Area for TestArea {
+        fn get(key: &str) -> Result<Option<String>, StorageError> {
+            Ok(STORE.with(|store| store.borrow().get(key).cloned()))
+        }
+
+        fn set(key: &str, val: &str) -> Result<Option<String>, StorageError> {
+            Ok(STORE.with(|store| store.borrow_mut().insert(key.to_string(), val.to_string())))
+        }
+
+        fn remove(key: &str) -> Result<Option<String>, StorageError> {
+            Ok(STORE.with(|store| store.borrow_mut().remove(key)))
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+    struct Settings {
+        volume: u8,
+    }
+
+    fn clear_store() {
+        STORE.with(|store| store.borrow_mut().clear());
+    }
+
+    #[test]
+    fn round_trips_through_get_set_remove() {
+        clear_store();
+        let field = Field::<Settings, TestArea>::namespaced("test", "settings");
+        assert_eq!(field.get(), None);
+        field.set(&Settings { volume: 7 });
+        assert_eq!(field.get(), Some(Settings { volume: 7 }));
+        assert_eq!(field.remove(), Some(Settings { volume: 7 }));
+        assert_eq!(field.get(), None);
+    }
+
+    #[test]
+    fn get_or_falls_back_on_missing_value() {
+        clear_store();
+        let field = Field::<Settings, TestArea>::namespaced("test", "fallback");
+        assert_eq!(field.get_or(Settings { volume: 3 }), Settings { volume: 3 });
+    }
+
+    #[test]
+    fn get_or_default_prefers_declared_default_over_type_default() {
+        clear_store();
+        let field = Field::<Settings, TestArea>::namespaced("test", "declared")
+            .with_default(Settings { volume: 11 });
+        assert_eq!(field.get_or_default(), Settings { volume: 11 });
+
+        field.set(&Settings { volume: 2 });
+        assert_eq!(field.get_or_default(), Settings { volume: 2 });
+    }
+
+    #[test]
+    fn get_or_default_falls_back_to_type_default_without_a_declared_default() {
+        clear_store();
+        let field = Field::<Settings, TestArea>::namespaced("test", "undeclared");
+        assert_eq!(field.get_or_default(), Settings::default());
+    }
+
+    #[test]
+    fn try_set_surfaces_the_underlying_error_instead_of_panicking() {
+        /// An [`Area`] whose every operation fails, to prove `Field::try_*` surfaces the error
+        /// rather than panicking.
+        struct FailingArea;
+
+        impl Area for FailingArea {
+            fn get(_key: &str) -> Result<Option<String>, StorageError> {
+                Err(StorageError::QuotaExceeded(wasm_bindgen::JsValue::NULL))
+            }
+
+            fn set(_key: &str, _val: &str) -> Result<Option<String>, StorageError> {
+                Err(StorageError::QuotaExceeded(wasm_bindgen::JsValue::NULL))
+            }
+
+            fn remove(_key: &str) -> Result<Option<String>, StorageError> {
+                Err(StorageError::QuotaExceeded(wasm_bindgen::JsValue::NULL))
+            }
+        }
+
+        let field = Field::<Settings, FailingArea>::namespaced("test", "failing");
+        assert!(matches!(
+            field.try_set(&Settings { volume: 1 }),
+            Err(StorageError::QuotaExceeded(_))
+        ));
+        assert!(matches!(
+            field.try_get(),
+            Err(StorageError::QuotaExceeded(_))
+        ));
+        assert!(matches!(
+            field.try_remove(),
+            Err(StorageError::QuotaExceeded(_))
+        ));
+    }
+}
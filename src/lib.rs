@@ -1,5 +1,16 @@
 use wasm_bindgen::prelude::*;
 
+mod backend;
+mod chunked;
+mod error;
+mod field;
+
+pub use backend::{Backend, WebBackend};
+#[cfg(feature = "mock")]
+pub use backend::MemoryBackend;
+pub use error::StorageError;
+pub use field::{Area, Field, Local, Session};
+
 macro_rules! impl_Storage {
     ($name:ident, $get:expr, $docname:expr) => {
         #[doc = "Access to the "]
@@ -12,7 +23,7 @@ macro_rules! impl_Storage {
                 let storage = $get
                     .throw_err()
                     .expect_throw(concat!($docname, " storage not available"));
-                crate::Storage::new(storage)
+                crate::Storage::new(crate::WebBackend::new(storage))
             }
 
             /// Get the key for the `idx`th item in the storage.
@@ -27,22 +38,48 @@ macro_rules! impl_Storage {
                 get_storage().get(key)
             }
 
+            /// Get a record from the storage if present, without panicking on error.
+            pub fn try_get(key: &str) -> Result<Option<String>, crate::StorageError> {
+                get_storage().try_get(key)
+            }
+
             /// Set a record in the storage and return the old record with the same key, if
             /// present.
             pub fn set(key: &str, val: &str) -> Option<String> {
                 get_storage().set(key, val)
             }
 
+            /// Set a record in the storage and return the old record with the same key, if
+            /// present, without panicking on error.
+            ///
+            /// This is the method to use if you want to handle
+            /// [`StorageError::QuotaExceeded`](crate::StorageError::QuotaExceeded) instead of
+            /// aborting.
+            pub fn try_set(key: &str, val: &str) -> Result<Option<String>, crate::StorageError> {
+                get_storage().try_set(key, val)
+            }
+
             /// Remove a record from the storage and return it, if present.
             pub fn remove(key: &str) -> Option<String> {
                 get_storage().remove(key)
             }
 
+            /// Remove a record from the storage and return it, if present, without panicking on
+            /// error.
+            pub fn try_remove(key: &str) -> Result<Option<String>, crate::StorageError> {
+                get_storage().try_remove(key)
+            }
+
             /// Remove all records from the storage.
             pub fn clear() {
                 get_storage().clear()
             }
 
+            /// Remove all records from the storage, without panicking on error.
+            pub fn try_clear() -> Result<(), crate::StorageError> {
+                get_storage().try_clear()
+            }
+
             /// An iterator over key/value pairs, in the order they were last modified (newest first) (I
             /// think).
             ///
@@ -55,6 +92,11 @@ macro_rules! impl_Storage {
             pub fn count() -> usize {
                 get_storage().count()
             }
+
+            #[cfg(test)]
+            pub(crate) fn storage() -> crate::Storage {
+                get_storage()
+            }
         }
     };
 }
@@ -62,73 +104,126 @@ macro_rules! impl_Storage {
 impl_Storage!(local, crate::window().local_storage(), "local");
 impl_Storage!(session, crate::window().session_storage(), "session");
 
+/// A key/value storage, generic over the [`Backend`] it's persisted to.
+///
+/// Defaults to [`WebBackend`] (a real `window.localStorage`/`sessionStorage`), which is what the
+/// [`local`]/[`session`] modules use under the hood. Swap in [`MemoryBackend`] (behind the
+/// `mock` feature) to unit-test persistence logic without a browser:
+///
+/// ```ignore
+/// let storage = Storage::new(MemoryBackend::default());
+/// ```
 #[derive(Debug, Clone)]
-struct Storage {
-    inner: web_sys::Storage,
+pub struct Storage<B: Backend = WebBackend> {
+    inner: B,
 }
 
-impl Storage {
-    fn new(inner: web_sys::Storage) -> Self {
-        // Casts only work when sizeof(usize) > sizeof(u32), which is ok on wasm.
-        debug_assert!(std::mem::size_of::<usize>() >= std::mem::size_of::<u32>());
+impl<B: Backend> Storage<B> {
+    pub fn new(inner: B) -> Self {
         Storage { inner }
     }
 
     /// Get the nth key from an index n.
-    fn key(&self, idx: usize) -> Option<String> {
-        // futureproof for 64 bit wasm
-        if idx > u32::max_value() as usize {
-            wasm_bindgen::throw_str("u32 overflow on position");
-        }
-        self.inner.key(idx as u32).throw_err()
+    pub fn key(&self, idx: usize) -> Option<String> {
+        self.inner.key(idx)
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.try_get(key)
+            .unwrap_or_else(|e| wasm_bindgen::throw_val(e.into_js()))
+    }
+
+    /// Get a record from the storage if present, without panicking on error.
+    pub fn try_get(&self, key: &str) -> Result<Option<String>, StorageError> {
+        self.inner.get(key)
+    }
+
+    pub fn set(&self, key: &str, val: &str) -> Option<String> {
+        self.try_set(key, val)
+            .unwrap_or_else(|e| wasm_bindgen::throw_val(e.into_js()))
     }
 
-    fn get(&self, key: &str) -> Option<String> {
-        self.inner.get_item(key).throw_err()
+    /// Set a record in the storage and return the old record with the same key, if present,
+    /// without panicking on error.
+    pub fn try_set(&self, key: &str, val: &str) -> Result<Option<String>, StorageError> {
+        self.inner.set(key, val)
     }
 
-    fn set(&self, key: &str, val: &str) -> Option<String> {
-        let old = self.get(key);
-        self.inner.set_item(key, val).throw_err();
-        old
+    pub fn remove(&self, key: &str) -> Option<String> {
+        self.try_remove(key)
+            .unwrap_or_else(|e| wasm_bindgen::throw_val(e.into_js()))
     }
 
-    fn remove(&self, key: &str) -> Option<String> {
-        let old = self.get(key);
-        self.inner.remove_item(key).throw_err();
-        old
+    /// Remove a record from the storage and return it, if present, without panicking on error.
+    pub fn try_remove(&self, key: &str) -> Result<Option<String>, StorageError> {
+        self.inner.remove(key)
     }
 
-    fn clear(&self) {
-        self.inner.clear().throw_err()
+    pub fn clear(&self) {
+        self.try_clear()
+            .unwrap_or_else(|e| wasm_bindgen::throw_val(e.into_js()))
+    }
+
+    /// Remove all records from the storage, without panicking on error.
+    pub fn try_clear(&self) -> Result<(), StorageError> {
+        self.inner.clear()
     }
 
     /// Get the number of records in the storage.
-    fn count(&self) -> usize {
-        self.inner.length().throw_err() as usize
+    pub fn count(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// An iterator over key/value pairs, in the order they were last modified (newest first) (I
+    /// think).
+    ///
+    /// Editing the storage while iterating will invalidate the iterator.
+    pub fn iter(&self) -> StorageIter<'_, B> {
+        StorageIter {
+            position: 0,
+            inner: self,
+        }
+    }
+}
+
+/// A borrowing iterator over a [`Storage`]'s key/value pairs. See [`Storage::iter`].
+pub struct StorageIter<'a, B: Backend> {
+    position: usize,
+    inner: &'a Storage<B>,
+}
+
+impl<'a, B: Backend> Iterator for StorageIter<'a, B> {
+    type Item = (String, String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.inner.key(self.position)?;
+        let value = self.inner.get(&key).unwrap_throw();
+        self.position += 1;
+        Some((key, value))
     }
 }
 
-impl IntoIterator for Storage {
+impl<B: Backend> IntoIterator for Storage<B> {
     type Item = (String, String);
-    type IntoIter = StorageIntoIter;
+    type IntoIter = StorageIntoIter<B>;
     fn into_iter(self) -> Self::IntoIter {
         StorageIntoIter::new(self)
     }
 }
 
-struct StorageIntoIter {
+/// A consuming iterator over a [`Storage`]'s key/value pairs. See [`Storage::into_iter`].
+pub struct StorageIntoIter<B: Backend> {
     position: usize,
-    inner: Storage,
+    inner: Storage<B>,
 }
 
-impl StorageIntoIter {
-    fn new(inner: Storage) -> Self {
+impl<B: Backend> StorageIntoIter<B> {
+    fn new(inner: Storage<B>) -> Self {
         StorageIntoIter { position: 0, inner }
     }
 }
 
-impl Iterator for StorageIntoIter {
+impl<B: Backend> Iterator for StorageIntoIter<B> {
     type Item = (String, String);
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -163,7 +258,7 @@ mod tests {
     use wasm_bindgen_test::*;
     wasm_bindgen_test_configure!(run_in_browser);
 
-    fn test_storage<Type>(storage: crate::Storage<Type>) {
+    fn test_storage<B: crate::Backend>(storage: crate::Storage<B>) {
         assert_eq!(storage.count(), 0);
         assert!(storage.set("first", "a_val").is_none());
         assert_eq!(storage.count(), 1);
@@ -184,11 +279,17 @@ mod tests {
 
     #[wasm_bindgen_test]
     fn local() {
-        test_storage(crate::local());
+        test_storage(crate::local::storage());
     }
 
     #[wasm_bindgen_test]
     fn session() {
-        test_storage(crate::session());
+        test_storage(crate::session::storage());
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn memory_backend() {
+        test_storage(crate::Storage::new(crate::MemoryBackend::default()));
     }
 }